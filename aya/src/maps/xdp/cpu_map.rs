@@ -0,0 +1,219 @@
+//! An array of available CPUs.
+
+use std::{
+    borrow::{Borrow, BorrowMut},
+    fs,
+    io,
+    os::fd::AsRawFd,
+};
+
+use aya_obj::generated::{bpf_cpumap_val, bpf_cpumap_val__bindgen_ty_1};
+
+use crate::{
+    maps::{check_bounds, check_kv_size, IterableMap, MapData, MapError},
+    programs::ProgramFd,
+    sys::{bpf_map_lookup_elem, bpf_map_update_elem},
+    Pod, FEATURES,
+};
+
+/// The path used to read the set of CPUs that are online on the host.
+const ONLINE_CPUS: &str = "/sys/devices/system/cpu/online";
+
+/// An array of available CPUs.
+///
+/// XDP programs can use this map to redirect packets to a target CPU for continued processing.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.15.
+///
+/// # Examples
+/// ```no_run
+/// # let mut bpf = aya::Bpf::load(&[])?;
+/// use aya::maps::xdp::CpuMap;
+///
+/// let mut cpumap = CpuMap::try_from(bpf.map_mut("CPUS").unwrap())?;
+/// let flags = 0;
+/// let queue_size = 2048;
+/// cpumap.set(0, queue_size, None, flags);
+///
+/// # Ok::<(), aya::BpfError>(())
+/// ```
+#[doc(alias = "BPF_MAP_TYPE_CPUMAP")]
+pub struct CpuMap<T> {
+    inner: T,
+    online: Vec<u32>,
+}
+
+impl<T: Borrow<MapData>> CpuMap<T> {
+    pub(crate) fn new(map: T) -> Result<CpuMap<T>, MapError> {
+        let data = map.borrow();
+
+        if FEATURES.cpumap_prog_id {
+            check_kv_size::<u32, bpf_cpumap_val>(data)?;
+        } else {
+            check_kv_size::<u32, u32>(data)?;
+        }
+
+        let _fd = data.fd_or_err()?;
+        let online = online_cpus().map_err(MapError::IoError)?;
+
+        Ok(CpuMap { inner: map, online })
+    }
+
+    /// Returns the number of elements in the array.
+    ///
+    /// This corresponds to the value of `bpf_map_def::max_entries` on the eBPF side.
+    pub fn len(&self) -> u32 {
+        self.inner.borrow().obj.max_entries()
+    }
+
+    /// Returns the queue size and possible program for a given CPU index.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if `cpu_index` is out of bounds, [`MapError::SyscallError`]
+    /// if `bpf_map_lookup_elem` fails.
+    pub fn get(&self, cpu_index: u32, flags: u64) -> Result<CpuMapValue, MapError> {
+        let data = self.inner.borrow();
+        check_bounds(data, cpu_index)?;
+        let fd = data.fd_or_err()?;
+
+        let value = if FEATURES.cpumap_prog_id {
+            bpf_map_lookup_elem::<_, bpf_cpumap_val>(fd, &cpu_index, flags).map(|value| {
+                value.map(|value| CpuMapValue {
+                    qsize: value.qsize,
+                    // SAFETY: map writes use fd, map reads use id.
+                    // https://elixir.bootlin.com/linux/v6.2/source/include/uapi/linux/bpf.h#L6465
+                    prog_id: unsafe { value.bpf_prog.id },
+                })
+            })
+        } else {
+            bpf_map_lookup_elem::<_, u32>(fd, &cpu_index, flags).map(|value| {
+                value.map(|qsize| CpuMapValue { qsize, prog_id: 0 })
+            })
+        };
+        value
+            .map_err(|(_, io_error)| MapError::SyscallError {
+                call: "bpf_map_lookup_elem".to_owned(),
+                io_error,
+            })?
+            .ok_or(MapError::KeyNotFound)
+    }
+
+    /// An iterator over the elements of the array. The iterator item type is `Result<CpuMapValue,
+    /// MapError>`.
+    pub fn iter(&self) -> impl Iterator<Item = Result<CpuMapValue, MapError>> + '_ {
+        (0..self.len()).map(move |i| self.get(i, 0))
+    }
+}
+
+impl<T: BorrowMut<MapData>> CpuMap<T> {
+    /// Sets the queue size at the given CPU index, and optionally a chained program.
+    ///
+    /// When redirecting to `cpu_index`, packets will be enqueued on a ring buffer of `queue_size`
+    /// entries owned by that CPU and processed there.
+    ///
+    /// An optional XDP program can be passed that will be run on the target CPU before the packet
+    /// is passed up the stack. Note that only XDP programs with the `map = "cpumap"` argument can
+    /// be passed. See the kernel-space `aya_bpf::xdp` for more information.
+    ///
+    /// The `cpu_index` must refer to a CPU that is online on the host, as reported by
+    /// `/sys/devices/system/cpu/online`; redirecting to an offline CPU would be silently dropped
+    /// by the kernel.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::InvalidCpu`] if `cpu_index` refers to a CPU that is not online,
+    /// [`MapError::OutOfBounds`] if `cpu_index` is out of bounds, [`MapError::SyscallError`] if
+    /// `bpf_map_update_elem` fails, [`MapError::ProgIdNotSupported`] if the kernel does not support
+    /// program ids and one is provided.
+    pub fn set(
+        &mut self,
+        cpu_index: u32,
+        queue_size: u32,
+        program: Option<ProgramFd>,
+        flags: u64,
+    ) -> Result<(), MapError> {
+        if !self.online.contains(&cpu_index) {
+            return Err(MapError::InvalidCpu { cpu: cpu_index });
+        }
+
+        let data = self.inner.borrow_mut();
+        check_bounds(data, cpu_index)?;
+        let fd = data.fd_or_err()?;
+
+        let res = if FEATURES.cpumap_prog_id {
+            let value = bpf_cpumap_val {
+                qsize: queue_size,
+                bpf_prog: bpf_cpumap_val__bindgen_ty_1 {
+                    fd: program.map(|prog| prog.as_raw_fd()).unwrap_or_default(),
+                },
+            };
+            bpf_map_update_elem(fd, Some(&cpu_index), &value, flags)
+        } else {
+            if program.is_some() {
+                return Err(MapError::ProgIdNotSupported);
+            }
+            bpf_map_update_elem(fd, Some(&cpu_index), &queue_size, flags)
+        };
+
+        res.map_err(|(_, io_error)| MapError::SyscallError {
+            call: "bpf_map_update_elem".to_owned(),
+            io_error,
+        })?;
+        Ok(())
+    }
+}
+
+impl<T: Borrow<MapData>> IterableMap<u32, CpuMapValue> for CpuMap<T> {
+    fn map(&self) -> &MapData {
+        self.inner.borrow()
+    }
+
+    fn get(&self, key: &u32) -> Result<CpuMapValue, MapError> {
+        self.get(*key, 0)
+    }
+}
+
+/// Reads and parses the set of online CPUs from `/sys/devices/system/cpu/online`.
+///
+/// The file holds a comma-separated list of single CPU indices and inclusive ranges, e.g.
+/// `0-3,5,7-8`, which is expanded into the full list `[0, 1, 2, 3, 5, 7, 8]`.
+fn online_cpus() -> Result<Vec<u32>, io::Error> {
+    let data = fs::read_to_string(ONLINE_CPUS)?;
+    parse_cpu_ranges(data.trim())
+}
+
+fn parse_cpu_ranges(data: &str) -> Result<Vec<u32>, io::Error> {
+    let mut cpus = Vec::new();
+    for range in data.split(',') {
+        let range = range.trim();
+        if range.is_empty() {
+            continue;
+        }
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (parse_cpu(start)?, parse_cpu(end)?),
+            None => {
+                let cpu = parse_cpu(range)?;
+                (cpu, cpu)
+            }
+        };
+        cpus.extend(start..=end);
+    }
+    Ok(cpus)
+}
+
+fn parse_cpu(s: &str) -> Result<u32, io::Error> {
+    s.trim()
+        .parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid cpu index: {s}")))
+}
+
+unsafe impl Pod for bpf_cpumap_val {}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CpuMapValue {
+    pub qsize: u32,
+    pub prog_id: u32,
+}
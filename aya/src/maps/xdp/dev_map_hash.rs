@@ -0,0 +1,190 @@
+//! A hashmap of network devices.
+
+use std::{
+    borrow::{Borrow, BorrowMut},
+    os::fd::AsRawFd,
+};
+
+use aya_obj::generated::{bpf_devmap_val, bpf_devmap_val__bindgen_ty_1};
+
+use super::dev_map::DevMapValue;
+use crate::{
+    maps::{check_kv_size, IterableMap, MapData, MapError, MapIter, MapKeys},
+    programs::ProgramFd,
+    sys::{bpf_map_delete_elem, bpf_map_lookup_elem, bpf_map_update_elem},
+    FEATURES,
+};
+
+/// A hashmap of network devices.
+///
+/// XDP programs can use this map to redirect to other network devices. Unlike
+/// [`DevMap`](super::DevMap), the devices are keyed by an arbitrary `u32`
+/// ifindex rather than by a dense `0..max_entries` index, which is convenient
+/// when the set of ifindexes is sparse or non-contiguous.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.4.
+///
+/// # Examples
+/// ```no_run
+/// # let mut bpf = aya::Bpf::load(&[])?;
+/// use aya::maps::xdp::DevMapHash;
+///
+/// let mut devmap = DevMapHash::try_from(bpf.map_mut("IFACES").unwrap())?;
+/// let source = 32u32;
+/// let dest = 42u32;
+/// devmap.set(source, dest, None, 0);
+///
+/// # Ok::<(), aya::BpfError>(())
+/// ```
+#[doc(alias = "BPF_MAP_TYPE_DEVMAP_HASH")]
+pub struct DevMapHash<T> {
+    inner: T,
+}
+
+impl<T: Borrow<MapData>> DevMapHash<T> {
+    pub(crate) fn new(map: T) -> Result<DevMapHash<T>, MapError> {
+        let data = map.borrow();
+
+        if FEATURES.devmap_prog_id {
+            check_kv_size::<u32, bpf_devmap_val>(data)?;
+        } else {
+            check_kv_size::<u32, u32>(data)?;
+        }
+
+        let _fd = data.fd_or_err()?;
+
+        Ok(DevMapHash { inner: map })
+    }
+
+    /// Returns the target ifindex and possible program for a given ifindex key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::SyscallError`] if `bpf_map_lookup_elem` fails, or
+    /// [`MapError::KeyNotFound`] if there is no device with the given ifindex.
+    pub fn get(&self, ifindex: u32, flags: u64) -> Result<DevMapValue, MapError> {
+        let data = self.inner.borrow();
+        let fd = data.fd_or_err()?;
+
+        let value = if FEATURES.devmap_prog_id {
+            bpf_map_lookup_elem::<_, bpf_devmap_val>(fd, &ifindex, flags).map(|value| {
+                value.map(|value| DevMapValue {
+                    ifindex: value.ifindex,
+                    // SAFETY: map writes use fd, map reads use id.
+                    // https://elixir.bootlin.com/linux/v6.2/source/include/uapi/linux/bpf.h#L6149
+                    prog_id: unsafe { value.bpf_prog.id },
+                })
+            })
+        } else {
+            bpf_map_lookup_elem::<_, u32>(fd, &ifindex, flags).map(|value| {
+                value.map(|ifindex| DevMapValue {
+                    ifindex,
+                    prog_id: 0,
+                })
+            })
+        };
+        value
+            .map_err(|(_, io_error)| MapError::SyscallError {
+                call: "bpf_map_lookup_elem".to_owned(),
+                io_error,
+            })?
+            .ok_or(MapError::KeyNotFound)
+    }
+
+    /// An iterator over the elements of the map in arbitrary order. The iterator
+    /// item type is `Result<(u32, DevMapValue), MapError>`.
+    pub fn iter(&self) -> MapIter<'_, u32, DevMapValue, Self> {
+        MapIter::new(self)
+    }
+
+    /// An iterator over the keys of the map in arbitrary order. The iterator item
+    /// type is `Result<u32, MapError>`.
+    pub fn keys(&self) -> MapKeys<'_, u32> {
+        MapKeys::new(self.inner.borrow())
+    }
+
+    /// Returns the number of elements the map can hold.
+    ///
+    /// This corresponds to the value of `bpf_map_def::max_entries` on the eBPF side.
+    pub fn len(&self) -> u32 {
+        self.inner.borrow().obj.max_entries()
+    }
+}
+
+impl<T: BorrowMut<MapData>> DevMapHash<T> {
+    /// Inserts an ifindex and optionally a chained program in the map.
+    ///
+    /// When redirecting using `key`, packets will be transmitted by the interface with `ifindex`.
+    ///
+    /// Another XDP program can be passed in that will be run before actual transmission. It can be
+    /// used to modify the packet before transmission with NIC specific data (MAC address update,
+    /// checksum computations, etc) or other purposes.
+    ///
+    /// Note that only XDP programs with the `map = "devmap"` argument can be passed. See the
+    /// kernel-space `aya_bpf::xdp` for more information.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::SyscallError`] if `bpf_map_update_elem` fails,
+    /// [`MapError::ProgIdNotSupported`] if the kernel does not support program ids and one is
+    /// provided.
+    pub fn set(
+        &mut self,
+        key: u32,
+        ifindex: u32,
+        program: Option<ProgramFd>,
+        flags: u64,
+    ) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        let fd = data.fd_or_err()?;
+
+        let res = if FEATURES.devmap_prog_id {
+            let value = bpf_devmap_val {
+                ifindex,
+                bpf_prog: bpf_devmap_val__bindgen_ty_1 {
+                    fd: program.map(|prog| prog.as_raw_fd()).unwrap_or_default(),
+                },
+            };
+            bpf_map_update_elem(fd, Some(&key), &value, flags)
+        } else {
+            if program.is_some() {
+                return Err(MapError::ProgIdNotSupported);
+            }
+            bpf_map_update_elem(fd, Some(&key), &ifindex, flags)
+        };
+
+        res.map_err(|(_, io_error)| MapError::SyscallError {
+            call: "bpf_map_update_elem".to_owned(),
+            io_error,
+        })?;
+        Ok(())
+    }
+
+    /// Removes a value from the map.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::SyscallError`] if `bpf_map_delete_elem` fails.
+    pub fn remove(&mut self, key: u32) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        let fd = data.fd_or_err()?;
+        bpf_map_delete_elem(fd, &key)
+            .map(|_| ())
+            .map_err(|(_, io_error)| MapError::SyscallError {
+                call: "bpf_map_delete_elem".to_owned(),
+                io_error,
+            })
+    }
+}
+
+impl<T: Borrow<MapData>> IterableMap<u32, DevMapValue> for DevMapHash<T> {
+    fn map(&self) -> &MapData {
+        self.inner.borrow()
+    }
+
+    fn get(&self, key: &u32) -> Result<DevMapValue, MapError> {
+        self.get(*key, 0)
+    }
+}
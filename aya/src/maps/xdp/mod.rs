@@ -0,0 +1,11 @@
+//! XDP maps.
+
+mod cpu_map;
+mod dev_map;
+mod dev_map_hash;
+mod xsk_map;
+
+pub use cpu_map::{CpuMap, CpuMapValue};
+pub use dev_map::{DevMap, DevMapValue};
+pub use dev_map_hash::DevMapHash;
+pub use xsk_map::XskMap;
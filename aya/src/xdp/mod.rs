@@ -0,0 +1,15 @@
+//! AF_XDP socket support.
+//!
+//! This module provides a safe wrapper around the pieces needed to drive an `AF_XDP` socket from
+//! userspace: a [`Umem`] frame pool, the four rings (fill, completion, RX and TX) and the
+//! [`Socket`] type that ties them together. A bound [`Socket`] exposes [`AsRawFd`] so it can be
+//! inserted directly into an [`XskMap`](crate::maps::XskMap), giving a complete
+//! redirect-to-userspace path without hand-rolling `mmap`/`setsockopt`/ring management.
+//!
+//! [`AsRawFd`]: std::os::fd::AsRawFd
+
+mod socket;
+
+pub use socket::{
+    BindFlags, Frame, Socket, SocketConfig, SocketError, Umem, UmemConfig, XdpDesc,
+};
@@ -0,0 +1,668 @@
+//! An `AF_XDP` socket and its backing UMEM.
+
+use std::{
+    io,
+    mem,
+    os::fd::{AsRawFd, RawFd},
+    ptr,
+    slice,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use libc::{
+    c_void, sockaddr_xdp, xdp_desc, xdp_mmap_offsets, xdp_ring_offset, xdp_umem_reg, MAP_POPULATE,
+    MAP_SHARED, MSG_DONTWAIT, PROT_READ, PROT_WRITE, SOL_XDP, XDP_COPY, XDP_MMAP_OFFSETS,
+    XDP_PGOFF_RX_RING, XDP_PGOFF_TX_RING, XDP_RING_NEED_WAKEUP, XDP_RX_RING, XDP_SHARED_UMEM,
+    XDP_TX_RING, XDP_UMEM_COMPLETION_RING, XDP_UMEM_FILL_RING, XDP_UMEM_PGOFF_COMPLETION_RING,
+    XDP_UMEM_PGOFF_FILL_RING, XDP_UMEM_REG, XDP_USE_NEED_WAKEUP, XDP_ZEROCOPY,
+};
+
+/// Errors that can occur while creating or operating an [`AF_XDP`](Socket) socket.
+#[derive(thiserror::Error, Debug)]
+pub enum SocketError {
+    /// A syscall failed.
+    #[error("`{call}` failed")]
+    SyscallError {
+        /// The name of the failed libc call.
+        call: &'static str,
+        /// The underlying [`io::Error`].
+        #[source]
+        io_error: io::Error,
+    },
+    /// The UMEM was misconfigured.
+    #[error("invalid UMEM configuration: {0}")]
+    InvalidUmem(&'static str),
+}
+
+impl SocketError {
+    fn syscall(call: &'static str) -> SocketError {
+        SocketError::SyscallError {
+            call,
+            io_error: io::Error::last_os_error(),
+        }
+    }
+}
+
+/// Configuration for a [`Umem`] frame pool.
+#[derive(Clone, Copy, Debug)]
+pub struct UmemConfig {
+    /// The number of frames in the pool.
+    pub frame_count: u32,
+    /// The size of each frame in bytes. Must be a power of two, typically 2048 or 4096.
+    pub frame_size: u32,
+    /// The number of descriptors in the fill ring.
+    pub fill_size: u32,
+    /// The number of descriptors in the completion ring.
+    pub completion_size: u32,
+}
+
+impl Default for UmemConfig {
+    fn default() -> Self {
+        UmemConfig {
+            frame_count: 4096,
+            frame_size: 4096,
+            fill_size: 2048,
+            completion_size: 2048,
+        }
+    }
+}
+
+/// A memory region shared with the kernel, carved into fixed-size frames.
+///
+/// The UMEM is `mmap`'d anonymously and registered against the socket with `XDP_UMEM_REG`. Frames
+/// are addressed by their byte offset from the start of the region.
+pub struct Umem {
+    area: *mut u8,
+    len: usize,
+    config: UmemConfig,
+}
+
+// SAFETY: the mapping is owned by the `Umem` and only aliased through `&mut self` accessors.
+unsafe impl Send for Umem {}
+
+impl Umem {
+    fn new(config: UmemConfig) -> Result<Umem, SocketError> {
+        if !config.frame_size.is_power_of_two() {
+            return Err(SocketError::InvalidUmem("frame_size must be a power of two"));
+        }
+        if config.frame_count == 0 {
+            return Err(SocketError::InvalidUmem("frame_count must be non-zero"));
+        }
+
+        let len = config.frame_count as usize * config.frame_size as usize;
+        // SAFETY: `mmap` with a null address lets the kernel pick the mapping.
+        let area = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED | libc::MAP_ANONYMOUS | MAP_POPULATE,
+                -1,
+                0,
+            )
+        };
+        if area == libc::MAP_FAILED {
+            return Err(SocketError::syscall("mmap"));
+        }
+
+        Ok(Umem {
+            area: area as *mut u8,
+            len,
+            config,
+        })
+    }
+
+    fn register(&self, fd: RawFd) -> Result<(), SocketError> {
+        let reg = xdp_umem_reg {
+            addr: self.area as u64,
+            len: self.len as u64,
+            chunk_size: self.config.frame_size,
+            headroom: 0,
+            flags: 0,
+        };
+        setsockopt(fd, XDP_UMEM_REG, &reg)?;
+        setsockopt(fd, XDP_UMEM_FILL_RING, &self.config.fill_size)?;
+        setsockopt(fd, XDP_UMEM_COMPLETION_RING, &self.config.completion_size)?;
+        Ok(())
+    }
+
+    /// Returns a mutable view of the frame starting at `addr` for `len` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `addr + len` falls outside the mapped region.
+    pub fn frame(&mut self, addr: u64, len: usize) -> Frame<'_> {
+        let end = addr as usize + len;
+        assert!(end <= self.len, "frame out of bounds");
+        // SAFETY: bounds checked above, exclusive access via `&mut self`.
+        let bytes = unsafe { slice::from_raw_parts_mut(self.area.add(addr as usize), len) };
+        Frame { bytes }
+    }
+}
+
+impl Drop for Umem {
+    fn drop(&mut self) {
+        // SAFETY: `area`/`len` describe the mapping created in `new`.
+        unsafe {
+            libc::munmap(self.area as *mut c_void, self.len);
+        }
+    }
+}
+
+/// A mutable view of a single UMEM frame.
+pub struct Frame<'a> {
+    bytes: &'a mut [u8],
+}
+
+impl Frame<'_> {
+    /// The raw bytes backing this frame.
+    pub fn bytes(&mut self) -> &mut [u8] {
+        self.bytes
+    }
+}
+
+/// A single RX/TX descriptor.
+///
+/// `addr` is the byte offset of the frame within the [`Umem`] and `len` is the number of valid
+/// bytes in it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct XdpDesc {
+    /// The byte offset of the frame within the UMEM.
+    pub addr: u64,
+    /// The number of valid bytes in the frame.
+    pub len: u32,
+    /// Descriptor options, passed through to the kernel unchanged.
+    pub options: u32,
+}
+
+/// The mode used to bind an [`AF_XDP`](Socket) socket.
+#[derive(Clone, Copy, Debug)]
+pub struct BindFlags(u16);
+
+impl BindFlags {
+    /// Ask the driver for zero-copy mode.
+    pub const ZEROCOPY: BindFlags = BindFlags(XDP_ZEROCOPY as u16);
+    /// Force copy mode.
+    pub const COPY: BindFlags = BindFlags(XDP_COPY as u16);
+    /// Share the UMEM of a socket already bound to the same device.
+    pub const SHARED_UMEM: BindFlags = BindFlags(XDP_SHARED_UMEM as u16);
+    /// Use the need-wakeup flag to cooperate with the driver.
+    pub const NEED_WAKEUP: BindFlags = BindFlags(XDP_USE_NEED_WAKEUP as u16);
+
+    /// Combine two sets of flags.
+    pub const fn union(self, other: BindFlags) -> BindFlags {
+        BindFlags(self.0 | other.0)
+    }
+
+    /// Returns `true` if `self` contains all of the bits in `other`.
+    pub const fn contains(self, other: BindFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for BindFlags {
+    fn default() -> Self {
+        BindFlags::COPY
+    }
+}
+
+/// Configuration for a [`Socket`].
+#[derive(Clone, Copy, Debug)]
+pub struct SocketConfig {
+    /// The UMEM to allocate and register.
+    pub umem: UmemConfig,
+    /// The number of descriptors in the RX ring.
+    pub rx_size: u32,
+    /// The number of descriptors in the TX ring.
+    pub tx_size: u32,
+    /// The bind mode flags.
+    pub bind_flags: BindFlags,
+}
+
+impl Default for SocketConfig {
+    fn default() -> Self {
+        SocketConfig {
+            umem: UmemConfig::default(),
+            rx_size: 2048,
+            tx_size: 2048,
+            bind_flags: BindFlags::default(),
+        }
+    }
+}
+
+/// An `AF_XDP` socket bound to an interface queue.
+///
+/// A `Socket` owns its [`Umem`] and the four rings. Use [`fill`](Socket::fill) to hand frames to
+/// the kernel, [`rx`](Socket::rx) to iterate received descriptors, [`tx`](Socket::tx) to queue
+/// frames for transmission and [`complete`](Socket::complete) to reclaim transmitted frames.
+///
+/// # Examples
+/// ```no_run
+/// use aya::xdp::{Socket, SocketConfig};
+///
+/// let mut socket = Socket::new(&SocketConfig::default(), 3 /* ifindex */, 0 /* queue */)?;
+/// # Ok::<(), aya::xdp::SocketError>(())
+/// ```
+pub struct Socket {
+    fd: RawFd,
+    umem: Umem,
+    fill: Ring,
+    completion: Ring,
+    rx: Ring,
+    tx: Ring,
+    need_wakeup: bool,
+}
+
+impl Socket {
+    /// Creates an `AF_XDP` socket, allocates its UMEM and binds it to `ifindex`/`queue_id`.
+    pub fn new(config: &SocketConfig, ifindex: u32, queue_id: u32) -> Result<Socket, SocketError> {
+        // SAFETY: `socket(2)` with the documented arguments.
+        let fd = unsafe { libc::socket(libc::AF_XDP, libc::SOCK_RAW, 0) };
+        if fd < 0 {
+            return Err(SocketError::syscall("socket"));
+        }
+
+        let umem = Umem::new(config.umem)?;
+        if let Err(e) = umem.register(fd) {
+            close(fd);
+            return Err(e);
+        }
+
+        if let Err(e) = setsockopt(fd, XDP_RX_RING, &config.rx_size)
+            .and_then(|_| setsockopt(fd, XDP_TX_RING, &config.tx_size))
+        {
+            close(fd);
+            return Err(e);
+        }
+
+        let offsets = match mmap_offsets(fd) {
+            Ok(offsets) => offsets,
+            Err(e) => {
+                close(fd);
+                return Err(e);
+            }
+        };
+
+        let rings = (|| {
+            let fill = Ring::map(
+                fd,
+                config.umem.fill_size,
+                mem::size_of::<u64>(),
+                &offsets.fr,
+                XDP_UMEM_PGOFF_FILL_RING as i64,
+            )?;
+            let completion = Ring::map(
+                fd,
+                config.umem.completion_size,
+                mem::size_of::<u64>(),
+                &offsets.cr,
+                XDP_UMEM_PGOFF_COMPLETION_RING as i64,
+            )?;
+            let rx = Ring::map(
+                fd,
+                config.rx_size,
+                mem::size_of::<xdp_desc>(),
+                &offsets.rx,
+                XDP_PGOFF_RX_RING as i64,
+            )?;
+            let tx = Ring::map(
+                fd,
+                config.tx_size,
+                mem::size_of::<xdp_desc>(),
+                &offsets.tx,
+                XDP_PGOFF_TX_RING as i64,
+            )?;
+            Ok((fill, completion, rx, tx))
+        })();
+
+        let (fill, completion, rx, tx) = match rings {
+            Ok(rings) => rings,
+            Err(e) => {
+                close(fd);
+                return Err(e);
+            }
+        };
+
+        let mut sxdp: sockaddr_xdp = unsafe { mem::zeroed() };
+        sxdp.sxdp_family = libc::AF_XDP as u16;
+        sxdp.sxdp_ifindex = ifindex;
+        sxdp.sxdp_queue_id = queue_id;
+        sxdp.sxdp_flags = config.bind_flags.0;
+
+        // SAFETY: `sxdp` is a fully initialized `sockaddr_xdp`.
+        let res = unsafe {
+            libc::bind(
+                fd,
+                &sxdp as *const _ as *const libc::sockaddr,
+                mem::size_of::<sockaddr_xdp>() as u32,
+            )
+        };
+        if res < 0 {
+            close(fd);
+            return Err(SocketError::syscall("bind"));
+        }
+
+        Ok(Socket {
+            fd,
+            umem,
+            fill,
+            completion,
+            rx,
+            tx,
+            need_wakeup: config.bind_flags.contains(BindFlags::NEED_WAKEUP),
+        })
+    }
+
+    /// A mutable view of the UMEM frame pool.
+    pub fn umem(&mut self) -> &mut Umem {
+        &mut self.umem
+    }
+
+    /// Hands the given frame addresses to the kernel on the fill ring, making them available for
+    /// the driver to receive into. Returns the number of addresses actually enqueued.
+    pub fn fill(&mut self, addrs: &[u64]) -> u32 {
+        let n = self.fill.reserve(addrs.len() as u32);
+        for (i, addr) in addrs.iter().take(n as usize).enumerate() {
+            // SAFETY: index is within the reserved region.
+            unsafe { *self.fill.slot::<u64>(i as u32) = *addr };
+        }
+        self.fill.submit(n);
+        // The fill ring is driven by the driver's RX path; it only has to be woken explicitly when
+        // the socket was bound with `NEED_WAKEUP` and the driver has asked for a wakeup.
+        if self.fill.needs_wakeup() {
+            self.wake_rx();
+        }
+        n
+    }
+
+    /// Reclaims up to `max` transmitted frame addresses from the completion ring.
+    pub fn complete(&mut self, max: u32) -> Vec<u64> {
+        let n = self.completion.peek(max);
+        let mut addrs = Vec::with_capacity(n as usize);
+        for i in 0..n {
+            // SAFETY: index is within the peeked region.
+            addrs.push(unsafe { *self.completion.slot::<u64>(i) });
+        }
+        self.completion.release(n);
+        addrs
+    }
+
+    /// An iterator over the descriptors available on the RX ring, up to `max`. The consumer index
+    /// is advanced once the iterator is dropped.
+    pub fn rx(&mut self, max: u32) -> RxIter<'_> {
+        let n = self.rx.peek(max);
+        RxIter {
+            ring: &mut self.rx,
+            pos: 0,
+            len: n,
+        }
+    }
+
+    /// Queues the given descriptors for transmission on the TX ring. Returns the number of
+    /// descriptors actually enqueued.
+    pub fn tx(&mut self, descs: &[XdpDesc]) -> u32 {
+        let n = self.tx.reserve(descs.len() as u32);
+        for (i, desc) in descs.iter().take(n as usize).enumerate() {
+            // SAFETY: index is within the reserved region.
+            let slot = unsafe { &mut *self.tx.slot::<xdp_desc>(i as u32) };
+            slot.addr = desc.addr;
+            slot.len = desc.len;
+            slot.options = desc.options;
+        }
+        self.tx.submit(n);
+        // The TX ring does not drain on a producer-index update alone; the socket must be kicked.
+        // In `NEED_WAKEUP` mode the kick is elided unless the driver has asked for one.
+        if !self.need_wakeup || self.tx.needs_wakeup() {
+            self.kick_tx();
+        }
+        n
+    }
+
+    /// Kicks the TX ring by issuing a non-blocking `sendto`, asking the driver to transmit any
+    /// queued descriptors. Transient errors (`EAGAIN`, `EBUSY`, `ENOBUFS`) are expected under load
+    /// and ignored.
+    fn kick_tx(&self) {
+        // SAFETY: a null buffer of length 0 is valid for `sendto` on an AF_XDP socket.
+        unsafe {
+            libc::sendto(
+                self.fd,
+                ptr::null(),
+                0,
+                MSG_DONTWAIT,
+                ptr::null(),
+                0,
+            );
+        }
+    }
+
+    /// Wakes the driver's RX/fill path with a non-blocking `recvfrom`. Transient errors are
+    /// expected and ignored.
+    fn wake_rx(&self) {
+        // SAFETY: a null buffer of length 0 is valid for `recvfrom` on an AF_XDP socket.
+        unsafe {
+            libc::recvfrom(
+                self.fd,
+                ptr::null_mut(),
+                0,
+                MSG_DONTWAIT,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+        }
+    }
+}
+
+impl AsRawFd for Socket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        close(self.fd);
+    }
+}
+
+/// An iterator over received descriptors, yielding [`XdpDesc`]. Advancing the RX ring's consumer
+/// index happens when the iterator is dropped.
+pub struct RxIter<'a> {
+    ring: &'a mut Ring,
+    pos: u32,
+    len: u32,
+}
+
+impl Iterator for RxIter<'_> {
+    type Item = XdpDesc;
+
+    fn next(&mut self) -> Option<XdpDesc> {
+        if self.pos >= self.len {
+            return None;
+        }
+        // SAFETY: index is within the peeked region.
+        let desc = unsafe { &*self.ring.slot::<xdp_desc>(self.pos) };
+        let out = XdpDesc {
+            addr: desc.addr,
+            len: desc.len,
+            options: desc.options,
+        };
+        self.pos += 1;
+        Some(out)
+    }
+}
+
+impl Drop for RxIter<'_> {
+    fn drop(&mut self) {
+        // Only release what the caller actually consumed. Releasing the full peeked count would
+        // mark unread descriptors as consumed when the caller breaks out of the loop early,
+        // dropping those packets and leaking their frames.
+        self.ring.release(self.pos);
+    }
+}
+
+/// A single-producer/single-consumer ring shared with the kernel.
+struct Ring {
+    mmap: *mut c_void,
+    mmap_len: usize,
+    producer: *const AtomicU32,
+    consumer: *const AtomicU32,
+    flags: *const AtomicU32,
+    ring: *mut u8,
+    entry_size: usize,
+    mask: u32,
+    cached_prod: u32,
+    cached_cons: u32,
+}
+
+// SAFETY: a `Ring` is only ever accessed through `&mut Socket`.
+unsafe impl Send for Ring {}
+
+impl Ring {
+    fn map(
+        fd: RawFd,
+        size: u32,
+        entry_size: usize,
+        off: &xdp_ring_offset,
+        pgoff: i64,
+    ) -> Result<Ring, SocketError> {
+        let mmap_len = off.desc as usize + size as usize * entry_size;
+        // SAFETY: `mmap` of the kernel-provided ring at the documented page offset.
+        let mmap = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                mmap_len,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED | MAP_POPULATE,
+                fd,
+                pgoff,
+            )
+        };
+        if mmap == libc::MAP_FAILED {
+            return Err(SocketError::syscall("mmap"));
+        }
+
+        let base = mmap as *mut u8;
+        Ok(Ring {
+            mmap,
+            mmap_len,
+            // SAFETY: offsets come from `XDP_MMAP_OFFSETS` for this ring.
+            producer: unsafe { base.add(off.producer as usize) } as *const AtomicU32,
+            consumer: unsafe { base.add(off.consumer as usize) } as *const AtomicU32,
+            flags: unsafe { base.add(off.flags as usize) } as *const AtomicU32,
+            ring: unsafe { base.add(off.desc as usize) },
+            entry_size,
+            mask: size - 1,
+            cached_prod: 0,
+            cached_cons: 0,
+        })
+    }
+
+    #[inline]
+    fn prod(&self) -> &AtomicU32 {
+        // SAFETY: pointer valid for the lifetime of the ring.
+        unsafe { &*self.producer }
+    }
+
+    #[inline]
+    fn cons(&self) -> &AtomicU32 {
+        // SAFETY: pointer valid for the lifetime of the ring.
+        unsafe { &*self.consumer }
+    }
+
+    /// Returns `true` if the driver has set the `NEED_WAKEUP` flag on this ring, indicating that
+    /// it must be kicked before it will make progress.
+    #[inline]
+    fn needs_wakeup(&self) -> bool {
+        // SAFETY: pointer valid for the lifetime of the ring.
+        let flags = unsafe { &*self.flags };
+        flags.load(Ordering::Acquire) & XDP_RING_NEED_WAKEUP != 0
+    }
+
+    /// Returns a pointer to the `idx`-th in-flight slot, accounting for the ring mask.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be within the region reserved or peeked by the caller.
+    unsafe fn slot<T>(&self, idx: u32) -> *mut T {
+        let slot = (self.cached_cons.wrapping_add(idx) & self.mask) as usize;
+        self.ring.add(slot * self.entry_size) as *mut T
+    }
+
+    /// Reserve up to `n` producer slots, returning how many are free.
+    fn reserve(&mut self, n: u32) -> u32 {
+        let free = self.mask + 1 - (self.cached_prod.wrapping_sub(self.cons().load(Ordering::Acquire)));
+        let n = n.min(free);
+        self.cached_cons = self.cached_prod;
+        n
+    }
+
+    /// Publish `n` produced slots to the kernel.
+    fn submit(&mut self, n: u32) {
+        self.cached_prod = self.cached_prod.wrapping_add(n);
+        self.prod().store(self.cached_prod, Ordering::Release);
+    }
+
+    /// Peek up to `n` consumer slots, returning how many are available.
+    fn peek(&mut self, n: u32) -> u32 {
+        let avail = self.prod().load(Ordering::Acquire).wrapping_sub(self.cached_cons);
+        n.min(avail)
+    }
+
+    /// Release `n` consumed slots back to the kernel.
+    fn release(&mut self, n: u32) {
+        self.cached_cons = self.cached_cons.wrapping_add(n);
+        self.cons().store(self.cached_cons, Ordering::Release);
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        // SAFETY: `mmap`/`mmap_len` describe the mapping created in `map`.
+        unsafe {
+            libc::munmap(self.mmap, self.mmap_len);
+        }
+    }
+}
+
+fn setsockopt<T>(fd: RawFd, opt: i32, value: &T) -> Result<(), SocketError> {
+    // SAFETY: `value` outlives the call and `len` matches its size.
+    let res = unsafe {
+        libc::setsockopt(
+            fd,
+            SOL_XDP,
+            opt,
+            value as *const _ as *const c_void,
+            mem::size_of::<T>() as u32,
+        )
+    };
+    if res < 0 {
+        return Err(SocketError::syscall("setsockopt"));
+    }
+    Ok(())
+}
+
+fn mmap_offsets(fd: RawFd) -> Result<xdp_mmap_offsets, SocketError> {
+    let mut offsets: xdp_mmap_offsets = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<xdp_mmap_offsets>() as u32;
+    // SAFETY: `offsets` is sized to receive the full structure.
+    let res = unsafe {
+        libc::getsockopt(
+            fd,
+            SOL_XDP,
+            XDP_MMAP_OFFSETS,
+            &mut offsets as *mut _ as *mut c_void,
+            &mut len,
+        )
+    };
+    if res < 0 {
+        return Err(SocketError::syscall("getsockopt"));
+    }
+    Ok(offsets)
+}
+
+fn close(fd: RawFd) {
+    // SAFETY: `fd` is owned and closed exactly once.
+    unsafe {
+        libc::close(fd);
+    }
+}
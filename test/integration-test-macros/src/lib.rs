@@ -15,14 +15,26 @@ pub fn integration_test(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     // Wrap in a netns exec
     let mut netns = false;
+    let mut min_kernel: Option<(u8, u8, u8)> = None;
     let parser = syn::meta::parser(|meta| {
         if meta.path.is_ident("netns") {
-            netns = true
+            netns = true;
+            Ok(())
+        } else if meta.path.is_ident("min_kernel") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            min_kernel = Some(parse_kernel_version(&value)?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported integration_test argument"))
         }
-        Ok(())
     });
     parse_macro_input!(attr with parser);
 
+    let min_kernel = match min_kernel {
+        Some((major, minor, patch)) => quote!(Some((#major, #minor, #patch))),
+        None => quote!(None),
+    };
+
     let item = if netns {
         // A vec cannot be directly expanded, and an empty #[] yields errors...
         let attrs = if attrs.is_empty() {
@@ -48,11 +60,36 @@ pub fn integration_test(attr: TokenStream, item: TokenStream) -> TokenStream {
         inventory::submit!(crate::IntegrationTest {
             name: concat!(module_path!(), "::", #name_str),
             test_fn: #name,
+            min_kernel: #min_kernel,
         });
     };
     TokenStream::from(expanded)
 }
 
+/// Parses a `major.minor[.patch]` kernel version string into its components.
+fn parse_kernel_version(value: &syn::LitStr) -> syn::Result<(u8, u8, u8)> {
+    let s = value.value();
+    let mut parts = s.split('.');
+    let mut next = |default: u8| -> syn::Result<u8> {
+        match parts.next() {
+            Some(part) => part
+                .parse()
+                .map_err(|_| syn::Error::new(value.span(), "invalid kernel version component")),
+            None => Ok(default),
+        }
+    };
+    let major = next(0)?;
+    let minor = next(0)?;
+    let patch = next(0)?;
+    if parts.next().is_some() {
+        return Err(syn::Error::new(
+            value.span(),
+            "expected a `major.minor[.patch]` kernel version",
+        ));
+    }
+    Ok((major, minor, patch))
+}
+
 #[proc_macro_attribute]
 pub fn tokio_integration_test(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let item = parse_macro_input!(item as ItemFn);
@@ -74,6 +111,7 @@ pub fn tokio_integration_test(_attr: TokenStream, item: TokenStream) -> TokenStr
         inventory::submit!(crate::IntegrationTest {
             name: concat!(module_path!(), "::", #sync_name_str),
             test_fn: #sync_name,
+            min_kernel: None,
         });
     };
     TokenStream::from(expanded)
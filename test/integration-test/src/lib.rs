@@ -0,0 +1,80 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// A registered integration test.
+///
+/// Tests are submitted via the [`integration_test`](integration_test_macros::integration_test)
+/// proc macro and collected at dispatch time through the [`inventory`] registry.
+#[derive(Debug)]
+pub struct IntegrationTest {
+    /// The fully qualified test name (`module_path!()::name`).
+    pub name: &'static str,
+    /// The test entry point.
+    pub test_fn: fn(),
+    /// The minimum kernel version `(major, minor, patch)` required to run this test, if any.
+    ///
+    /// Populated from `#[integration_test(min_kernel = "5.10")]`. When the running kernel is older
+    /// than this, the test is skipped rather than run.
+    pub min_kernel: Option<(u8, u8, u8)>,
+}
+
+inventory::collect!(IntegrationTest);
+
+/// The number of tests skipped during the last [`run`] because the running kernel was too old.
+static SKIPPED: AtomicU32 = AtomicU32::new(0);
+
+/// Runs every registered integration test, skipping those whose `min_kernel` requirement is not
+/// met by the running kernel. Returns the number of tests that were skipped.
+pub fn run() -> u32 {
+    SKIPPED.store(0, Ordering::Relaxed);
+    let running = kernel_version();
+    for test in inventory::iter::<IntegrationTest> {
+        if let Some(required) = test.min_kernel {
+            if running < required {
+                eprintln!(
+                    "skipped {}: requires kernel {}.{}.{}, running {}.{}.{}",
+                    test.name,
+                    required.0,
+                    required.1,
+                    required.2,
+                    running.0,
+                    running.1,
+                    running.2,
+                );
+                SKIPPED.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+        }
+        (test.test_fn)();
+    }
+    SKIPPED.load(Ordering::Relaxed)
+}
+
+/// Returns the running kernel's `(major, minor, patch)` version, parsed from the `release` field
+/// of `uname(2)`.
+fn kernel_version() -> (u8, u8, u8) {
+    let mut utsname = unsafe { std::mem::zeroed::<libc::utsname>() };
+    let ret = unsafe { libc::uname(&mut utsname) };
+    assert_eq!(ret, 0, "uname failed");
+
+    // SAFETY: `release` is a NUL-terminated C string populated by `uname`.
+    let release = unsafe { std::ffi::CStr::from_ptr(utsname.release.as_ptr()) };
+    parse_kernel_version(&release.to_string_lossy())
+}
+
+/// Parses the leading `major.minor[.patch]` of a `uname` release string, ignoring any trailing
+/// suffix such as `-generic` or `-rc1`. Components above 255 (e.g. the patch level of `4.19.325`)
+/// saturate at 255.
+fn parse_kernel_version(release: &str) -> (u8, u8, u8) {
+    let digits = release
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()
+        .unwrap_or_default();
+    let mut parts = digits.split('.');
+    let mut next = || {
+        parts
+            .next()
+            .and_then(|p| p.parse::<u32>().ok())
+            .map_or(0, |v| v.min(u32::from(u8::MAX)) as u8)
+    };
+    (next(), next(), next())
+}